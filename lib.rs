@@ -0,0 +1,744 @@
+use std::fmt;
+use std::str::Chars;
+use std::iter::Peekable;
+use std::collections::HashMap;
+use MathToken::*;
+use MathError::*;
+
+
+pub struct Environment {
+    vars: HashMap<String, f64>,
+}
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Environment {
+    pub fn new() -> Self {
+        Environment { vars: HashMap::new() }
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.vars.get(name).copied()
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.vars.insert(name.to_string(), value);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Copy, Clone)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Neg,
+    Pos,
+    ParOpen,
+    ParClose,
+}
+impl Op {
+    fn from_char(ch: char) -> Self {
+        match ch {
+            '+' => Op::Add,
+            '-' => Op::Sub,
+            '*' => Op::Mul,
+            '/' => Op::Div,
+            '^' => Op::Pow,
+            '(' => Op::ParOpen,
+            ')' => Op::ParClose,
+            _ => unreachable!()
+        }
+    }
+
+    fn is_unary(&self) -> bool {
+        matches!(self, Op::Neg | Op::Pos)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::ParOpen | Op::ParClose => 0,
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+            // Unary minus/plus sit below `Pow` on purpose: `-2^2` should parse
+            // as `-(2^2) = -4`, matching the convention used by every common
+            // calculator and language with a `^`/`**` operator, rather than
+            // `(-2)^2 = 4`.
+            Op::Neg | Op::Pos => 3,
+            Op::Pow => 4,
+        }
+    }
+
+    fn associativity(&self) -> Associativity {
+        match self {
+            Op::Pow | Op::Neg | Op::Pos => Associativity::Right,
+            _ => Associativity::Left,
+        }
+    }
+
+    fn call(&self, x: f64, y: f64) -> f64 {
+        match self {
+            Op::Add => x + y,
+            Op::Sub => x - y,
+            Op::Mul => x * y,
+            Op::Div => x / y,
+            Op::Pow => x.powf(y),
+            _ => unreachable!()
+        }
+    }
+
+    fn call_unary(&self, x: f64) -> f64 {
+        match self {
+            Op::Neg => -x,
+            Op::Pos => x,
+            _ => unreachable!()
+        }
+    }
+}
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+            Op::Pow => '^',
+            Op::Neg => '-',
+            Op::Pos => '+',
+            Op::ParOpen => '(',
+            Op::ParClose => ')',
+        })
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Sqrt,
+    Abs,
+    Max,
+    Min,
+}
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Func::Sin),
+            "cos" => Some(Func::Cos),
+            "tan" => Some(Func::Tan),
+            "sqrt" => Some(Func::Sqrt),
+            "abs" => Some(Func::Abs),
+            "max" => Some(Func::Max),
+            "min" => Some(Func::Min),
+            _ => None
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Func::Max | Func::Min => 2,
+            _ => 1
+        }
+    }
+
+    fn call(&self, args: &[f64]) -> f64 {
+        match self {
+            Func::Sin => args[0].sin(),
+            Func::Cos => args[0].cos(),
+            Func::Tan => args[0].tan(),
+            Func::Sqrt => args[0].sqrt(),
+            Func::Abs => args[0].abs(),
+            Func::Max => args[0].max(args[1]),
+            Func::Min => args[0].min(args[1]),
+        }
+    }
+}
+impl fmt::Display for Func {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Tan => "tan",
+            Func::Sqrt => "sqrt",
+            Func::Abs => "abs",
+            Func::Max => "max",
+            Func::Min => "min",
+        })
+    }
+}
+
+
+#[derive(Clone)]
+pub enum MathToken {
+    Num(f64),
+    Oper(Op),
+    Func(Func),
+    Ident(String),
+    Comma,
+}
+impl fmt::Display for MathToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Num(float) => write!(f, "Num({:.3})", float),
+            Oper(oper) => write!(f, "Op({})", oper),
+            Func(func) => write!(f, "Func({})", func),
+            Ident(name) => write!(f, "Ident({})", name),
+            Comma => write!(f, "Comma"),
+        }
+    }
+}
+
+
+pub enum Expr {
+    Num(f64),
+    Ident(String),
+    UnaryOp(Op, Box<Expr>),
+    BinOp(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+impl Expr {
+    pub fn eval(&self, env: &Environment) -> Result<f64, MathError> {
+        match self {
+            Expr::Num(float) => Ok(*float),
+            Expr::Ident(name) => env.get(name).ok_or_else(|| UndefinedVariableError(name.clone())),
+            Expr::UnaryOp(op, x) => Ok(op.call_unary(x.eval(env)?)),
+            Expr::BinOp(op, x, y) => Ok(op.call(x.eval(env)?, y.eval(env)?)),
+            Expr::Call(func, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.eval(env)).collect::<Result<_, _>>()?;
+                Ok(func.call(&args))
+            }
+        }
+    }
+
+    pub fn simplify(self) -> Result<Expr, MathError> {
+        match self {
+            Expr::Num(_) | Expr::Ident(_) => Ok(self),
+            Expr::UnaryOp(op, x) => match x.simplify()? {
+                Expr::Num(x) => Ok(Expr::Num(op.call_unary(x))),
+                x => Ok(Expr::UnaryOp(op, Box::new(x))),
+            },
+            Expr::BinOp(op, x, y) => {
+                let x = x.simplify()?;
+                let y = y.simplify()?;
+                // `x * 0 -> 0` is not folded for a non-constant `x`: it's false
+                // whenever `x` turns out to be NaN or +/-Infinity, and simplify
+                // has no way to prove finiteness for an operand it hasn't
+                // evaluated. The constant-folding arm below still folds `x * 0`
+                // when `x` is a constant.
+                match (op, x, y) {
+                    (Op::Div, _, Expr::Num(0.0)) => Err(DivisionByZeroError),
+                    (op, Expr::Num(x), Expr::Num(y)) => Ok(Expr::Num(op.call(x, y))),
+                    (Op::Mul, Expr::Num(n), other) | (Op::Mul, other, Expr::Num(n)) if n == 1.0 => Ok(other),
+                    (Op::Add, Expr::Num(n), other) | (Op::Add, other, Expr::Num(n)) if n == 0.0 => Ok(other),
+                    (Op::Sub, other, Expr::Num(0.0)) => Ok(other),
+                    (op, x, y) => Ok(Expr::BinOp(op, Box::new(x), Box::new(y))),
+                }
+            }
+            Expr::Call(func, args) => {
+                let args = args.into_iter().map(Expr::simplify).collect::<Result<Vec<_>, _>>()?;
+                if args.iter().all(|arg| matches!(arg, Expr::Num(_))) {
+                    let args: Vec<f64> = args.into_iter().map(|arg| match arg {
+                        Expr::Num(float) => float,
+                        _ => unreachable!()
+                    }).collect();
+                    Ok(Expr::Num(func.call(&args)))
+                } else {
+                    Ok(Expr::Call(func, args))
+                }
+            }
+        }
+    }
+}
+
+
+pub struct Tokens(Vec<MathToken>);
+impl Tokens {
+    fn parse(input: &str) -> Result<Tokens, MathError> {
+        let mut chars = input.chars().peekable();
+        let mut tokens = Vec::new();
+        let mut pos: usize = 0;
+
+        loop {
+            match chars.peek() {
+                Some('0'..='9' | '.') => tokens.push(Num(Tokens::parse_num(&mut chars, &mut pos)?)),
+                Some('+'|'-') => {
+                    let chr = chars.next().unwrap();
+                    pos += 1;
+                    let is_unary = match tokens.last() {
+                        None => true,
+                        Some(Comma) => true,
+                        Some(Oper(Op::ParClose)) => false,
+                        Some(Oper(_)) => true,
+                        Some(Num(_)) | Some(Func(_)) | Some(Ident(_)) => false,
+                    };
+                    tokens.push(Oper(if is_unary {
+                        if chr == '-' { Op::Neg } else { Op::Pos }
+                    } else {
+                        Op::from_char(chr)
+                    }));
+                },
+                Some('*'|'/'|'^'|'('|')') => { tokens.push(Oper(Op::from_char(chars.next().unwrap()))); pos += 1; },
+                Some(',') => { chars.next().unwrap(); pos += 1; tokens.push(Comma); },
+                Some(chr) if chr.is_alphabetic() => tokens.push(Tokens::parse_name(&mut chars, &mut pos)),
+                Some(chr) if chr.is_whitespace() => { chars.next().unwrap(); pos += 1; },
+                Some(_) => {
+                    let err_pos = pos;
+                    let chr = chars.next().unwrap();
+                    return Err(UnsupportedCharError(chr, err_pos));
+                },
+                None => return Ok(Tokens(tokens))
+            }
+        }
+    }
+
+    fn parse_name(input: &mut Peekable<Chars>, pos: &mut usize) -> MathToken {
+        let mut buf = String::new();
+
+        while matches!(input.peek(), Some(chr) if chr.is_alphabetic()) {
+            buf.push(input.next().unwrap());
+            *pos += 1;
+        }
+        while matches!(input.peek(), Some(chr) if chr.is_alphanumeric()) {
+            buf.push(input.next().unwrap());
+            *pos += 1;
+        }
+        match Func::from_name(&buf) {
+            Some(func) => Func(func),
+            None => Ident(buf),
+        }
+    }
+
+    fn parse_num(input: &mut Peekable<Chars>, pos: &mut usize) -> Result<f64, MathError> {
+        let start = *pos;
+        let mut buf = String::new();
+
+        while matches!(input.peek(), Some('0'..='9' | '.')) {
+            buf.push(input.next().unwrap());
+            *pos += 1;
+        }
+        return match buf.parse::<f64>() {
+            Ok(float) => Ok(float),
+            Err(_) => Err(ParseNumError(buf, start))
+        }
+    }
+
+    // Pops operators off `op_stack` onto `out_queue` until a `ParOpen` is
+    // reached, consuming that `ParOpen` too iff `consume_paren`. Returns
+    // false (instead of erroring directly) if the stack empties first, so
+    // callers can attach their own `MissingParens(self)` -- `self` isn't
+    // available here since it's borrowed apart into `op_stack`/`out_queue`.
+    fn pop_until_paropen(op_stack: &mut Vec<MathToken>, out_queue: &mut Vec<MathToken>, consume_paren: bool) -> bool {
+        loop {
+            match op_stack.last() {
+                None => return false,
+                Some(Oper(Op::ParOpen)) => {
+                    if consume_paren {
+                        op_stack.pop();
+                    }
+                    return true;
+                }
+                Some(_) => out_queue.push(op_stack.pop().unwrap()),
+            }
+        }
+    }
+
+    fn shunting(self) -> Result<Tokens, MathError> {
+        let mut op_stack: Vec<MathToken> = Vec::new();
+        let mut out_queue: Vec<MathToken> = Vec::new();
+
+        for token in &self.0 {
+            match token {
+                Num(_) | Ident(_) => out_queue.push(token.clone()),
+                Func(_) => op_stack.push(token.clone()),
+                Comma => {
+                    if !Tokens::pop_until_paropen(&mut op_stack, &mut out_queue, false) {
+                        return Err(MissingParens(self));
+                    }
+                }
+                Oper(op @ Op::ParOpen) => op_stack.push(Oper(*op)),
+                Oper(Op::ParClose) => {
+                    if !Tokens::pop_until_paropen(&mut op_stack, &mut out_queue, true) {
+                        return Err(MissingParens(self));
+                    }
+                    if matches!(op_stack.last(), Some(Func(_))) {
+                        out_queue.push(op_stack.pop().unwrap());
+                    }
+                }
+                Oper(oper) => {
+                    while let Some(Oper(prev)) = op_stack.last() {
+                        let should_pop = match oper.associativity() {
+                            Associativity::Left => prev.precedence() >= oper.precedence(),
+                            Associativity::Right => prev.precedence() > oper.precedence(),
+                        };
+                        if should_pop {
+                            out_queue.push(op_stack.pop().unwrap());
+                        } else {
+                            break;
+                        }
+                    }
+                    op_stack.push(token.clone());
+                }
+            }
+        }
+        while let Some(top) = op_stack.pop() {
+            if matches!(top, Oper(Op::ParOpen)) {
+                return Err(MissingParens(self));
+            }
+            out_queue.push(top);
+        }
+        Ok(Tokens(out_queue))
+    }
+
+    fn build_ast(self) -> Result<Expr, MathError> {
+        let mut stack: Vec<Expr> = Vec::new();
+
+        for token in &self.0 {
+            match token {
+                Num(float) => stack.push(Expr::Num(*float)),
+                Ident(name) => stack.push(Expr::Ident(name.clone())),
+                Comma => return Err(BadTokens("Stray comma outside of a function call.", self)),
+                Oper(oper) if oper.is_unary() => {
+                    if stack.len() < 1 {
+                        return Err(BadTokens("Not enough tokens to pop from stack.", self));
+                    }
+                    let x = stack.pop().unwrap();
+                    stack.push(Expr::UnaryOp(*oper, Box::new(x)));
+                }
+                Oper(oper) => {
+                    if stack.len() < 2 {
+                        return Err(BadTokens("Not enough tokens to pop from stack.", self));
+                    }
+                    let y = stack.pop().unwrap();
+                    let x = stack.pop().unwrap();
+                    stack.push(Expr::BinOp(*oper, Box::new(x), Box::new(y)));
+                }
+                Func(func) => {
+                    if stack.len() < func.arity() {
+                        return Err(BadTokens("Not enough tokens to pop from stack.", self));
+                    }
+                    let args = stack.split_off(stack.len() - func.arity());
+                    stack.push(Expr::Call(*func, args));
+                }
+            }
+        }
+        if stack.len() == 1 {
+            Ok(stack.pop().unwrap())
+        } else {
+            Err(BadTokens("Too many tokens left on stack.", self))
+        }
+    }
+
+    fn validate_ident(name: &str) -> Result<(), MathError> {
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(chr) if chr.is_alphabetic() => {}
+            _ => return Err(InvalidIdentifierError(name.to_string())),
+        }
+        if !chars.all(|chr| chr.is_alphanumeric()) {
+            return Err(InvalidIdentifierError(name.to_string()));
+        }
+        // `parse_name` always checks `Func::from_name` before falling back to
+        // `Ident`, so a variable shadowing a built-in name could never be read
+        // back. Reject it here instead of binding something unreachable.
+        if Func::from_name(name).is_some() {
+            return Err(InvalidIdentifierError(name.to_string()));
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tokens = self.0.iter();
+        if let Some(token) = tokens.next() {
+            match token {
+                Num(float) => write!(f, "{{{}", float)?,
+                Oper(oper) => write!(f, "{}", oper)?,
+                Func(func) => write!(f, "{}", func)?,
+                Ident(name) => write!(f, "{}", name)?,
+                Comma => write!(f, ",")?,
+            }
+        }
+        for token in tokens {
+            match token {
+                Num(float) => write!(f, ", {}", float)?,
+                Oper(oper) => write!(f, ", {}", oper)?,
+                Func(func) => write!(f, ", {}", func)?,
+                Ident(name) => write!(f, ", {}", name)?,
+                Comma => write!(f, ", ,")?,
+            }
+        }
+        write!(f, "{}", "}")
+    }
+}
+
+
+pub enum MathError {
+    ParseNumError(String, usize),
+    UnsupportedCharError(char, usize),
+    InvalidIdentifierError(String),
+    UndefinedVariableError(String),
+    DivisionByZeroError,
+    BadTokens(&'static str, Tokens),
+    MissingParens(Tokens),
+}
+impl MathError {
+    fn position(&self) -> Option<usize> {
+        match self {
+            ParseNumError(_, pos) => Some(*pos),
+            UnsupportedCharError(_, pos) => Some(*pos),
+            _ => None,
+        }
+    }
+
+    fn offset(self, delta: usize) -> Self {
+        match self {
+            ParseNumError(string, pos) => ParseNumError(string, pos + delta),
+            UnsupportedCharError(chr, pos) => UnsupportedCharError(chr, pos + delta),
+            other => other,
+        }
+    }
+
+    pub fn report(&self, input: &str) {
+        if let Some(pos) = self.position() {
+            println!("{}", input);
+            println!("{}^", " ".repeat(pos));
+        }
+        println!("{}", self);
+    }
+}
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseNumError(string, pos) => write!(f, "ParseNumError: `{}` at column {}", string, pos),
+            UnsupportedCharError(chr, pos) => write!(f, "UnsupportedCharError: `{}` at column {}", chr, pos),
+            InvalidIdentifierError(string) => write!(f, "InvalidIdentifierError: `{}`", string),
+            UndefinedVariableError(string) => write!(f, "UndefinedVariableError: `{}`", string),
+            DivisionByZeroError => write!(f, "DivisionByZeroError: division by zero"),
+            BadTokens(string, tokens) => write!(f, "BadTokens: {} ({})", tokens, string),
+            MissingParens(tokens) => write!(f, "MissingParens: {}", tokens),
+        }
+    }
+}
+
+
+pub fn parse_ast(input: &str) -> Result<Expr, MathError> {
+    Tokens::parse(input.trim())?.shunting()?.build_ast()
+}
+
+pub fn evaluate(input: &str) -> Result<f64, MathError> {
+    parse_ast(input)?.simplify()?.eval(&Environment::new())
+}
+
+pub fn handle_in(input: &str, env: &mut Environment) -> Result<f64, MathError> {
+    let trimmed = input.trim();
+    // `MathError` positions are char counts (`Tokens::parse` advances `pos`
+    // once per `char`), so these offsets must be char counts too, not byte
+    // offsets -- otherwise a multi-byte char before the split point skews them.
+    let (name, expr, offset) = match trimmed.find('=') {
+        Some(idx) => (Some(trimmed[..idx].trim()), &trimmed[idx + 1..], trimmed[..idx + 1].chars().count()),
+        None => (None, trimmed, 0),
+    };
+    if let Some(name) = name {
+        Tokens::validate_ident(name)?;
+    }
+
+    // `expr` is a slice of `trimmed`, but parse_ast() re-trims it before lexing,
+    // so positions in any resulting error are relative to that inner trim, not
+    // to `trimmed` as reported by `MathError::report`. Offset them back.
+    let leading_ws = expr.chars().take_while(|chr| chr.is_whitespace()).count();
+    let value = parse_ast(expr).map_err(|e| e.offset(offset + leading_ws))?.simplify()?.eval(env)?;
+    if let Some(name) = name {
+        env.set(name, value);
+    }
+    env.set("ans", value);
+    Ok(value)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(float: f64) -> Box<Expr> {
+        Box::new(Expr::Num(float))
+    }
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Ident(name.to_string()))
+    }
+
+    fn assert_num(expr: Expr, expected: f64) {
+        match expr.simplify() {
+            Ok(Expr::Num(got)) => assert_eq!(got, expected),
+            Ok(_) => panic!("expected Num({}), got a non-Num Expr", expected),
+            Err(_) => panic!("expected Num({}), got an Err", expected),
+        }
+    }
+
+    fn assert_ident(expr: Expr, expected: &str) {
+        match expr.simplify() {
+            Ok(Expr::Ident(got)) => assert_eq!(got, expected),
+            Ok(_) => panic!("expected Ident({}), got a different Expr", expected),
+            Err(_) => panic!("expected Ident({}), got an Err", expected),
+        }
+    }
+
+    #[test]
+    fn folds_constant_binops() {
+        assert_num(Expr::BinOp(Op::Add, num(2.0), num(3.0)), 5.0);
+        assert_num(Expr::BinOp(Op::Sub, num(5.0), num(1.0)), 4.0);
+        assert_num(Expr::BinOp(Op::Mul, num(2.0), num(3.0)), 6.0);
+        assert_num(Expr::BinOp(Op::Div, num(6.0), num(2.0)), 3.0);
+        assert_num(Expr::BinOp(Op::Pow, num(2.0), num(3.0)), 8.0);
+    }
+
+    #[test]
+    fn folds_constant_unary_ops() {
+        assert_num(Expr::UnaryOp(Op::Neg, num(3.0)), -3.0);
+        assert_num(Expr::UnaryOp(Op::Pos, num(3.0)), 3.0);
+    }
+
+    #[test]
+    fn folds_constant_function_calls() {
+        assert_num(Expr::Call(Func::Sqrt, vec![Expr::Num(4.0)]), 2.0);
+        assert_num(Expr::Call(Func::Max, vec![Expr::Num(1.0), Expr::Num(2.0)]), 2.0);
+    }
+
+    #[test]
+    fn collapses_multiplicative_identity() {
+        assert_ident(Expr::BinOp(Op::Mul, ident("x"), num(1.0)), "x");
+        assert_ident(Expr::BinOp(Op::Mul, num(1.0), ident("x")), "x");
+    }
+
+    #[test]
+    fn collapses_additive_identity() {
+        assert_ident(Expr::BinOp(Op::Add, ident("x"), num(0.0)), "x");
+        assert_ident(Expr::BinOp(Op::Add, num(0.0), ident("x")), "x");
+        assert_ident(Expr::BinOp(Op::Sub, ident("x"), num(0.0)), "x");
+    }
+
+    #[test]
+    fn folds_multiply_by_zero_only_for_constant_operands() {
+        assert_num(Expr::BinOp(Op::Mul, num(3.0), num(0.0)), 0.0);
+        assert_num(Expr::BinOp(Op::Mul, num(0.0), num(3.0)), 0.0);
+    }
+
+    #[test]
+    fn leaves_multiply_by_zero_unfolded_for_a_non_constant_operand() {
+        // `x * 0` is NOT folded to `0` when `x` isn't a known constant: that
+        // identity is false in IEEE-754 if `x` turns out to be NaN or
+        // +/-Infinity (`NaN * 0 = NaN`, `Inf * 0 = NaN`), and simplify can't
+        // prove finiteness for an operand it hasn't evaluated.
+        match Expr::BinOp(Op::Mul, ident("x"), num(0.0)).simplify() {
+            Ok(Expr::BinOp(Op::Mul, _, _)) => {}
+            _ => panic!("expected an unfolded BinOp(Mul, ..)"),
+        }
+        match Expr::BinOp(Op::Mul, num(0.0), ident("x")).simplify() {
+            Ok(Expr::BinOp(Op::Mul, _, _)) => {}
+            _ => panic!("expected an unfolded BinOp(Mul, ..)"),
+        }
+    }
+
+    #[test]
+    fn multiply_by_zero_with_nan_does_not_get_folded_away() {
+        let nan_times_zero = Expr::BinOp(Op::Mul, Box::new(Expr::Call(Func::Sqrt, vec![Expr::Num(-1.0)])), num(0.0));
+        match nan_times_zero.simplify().and_then(|e| e.eval(&Environment::new())) {
+            Ok(n) => assert!(n.is_nan(), "expected NaN, got {}", n),
+            Err(_) => panic!("expected an Ok(NaN) result"),
+        }
+    }
+
+    #[test]
+    fn division_by_zero_is_caught_at_fold_time() {
+        match Expr::BinOp(Op::Div, num(1.0), num(0.0)).simplify() {
+            Err(DivisionByZeroError) => {}
+            _ => panic!("expected DivisionByZeroError"),
+        }
+    }
+
+    #[test]
+    fn simplifies_bottom_up_through_nested_nodes() {
+        // (2 + 3) * x -> 5 * x, not left as BinOp(Mul, BinOp(Add, 2, 3), x)
+        let expr = Expr::BinOp(
+            Op::Mul,
+            Box::new(Expr::BinOp(Op::Add, num(2.0), num(3.0))),
+            ident("x"),
+        );
+        match expr.simplify() {
+            Ok(Expr::BinOp(Op::Mul, x, y)) => {
+                match (*x, *y) {
+                    (Expr::Num(n), Expr::Ident(name)) => {
+                        assert_eq!(n, 5.0);
+                        assert_eq!(name, "x");
+                    }
+                    _ => panic!("expected Num(5) * Ident(x)"),
+                }
+            }
+            _ => panic!("expected a BinOp(Mul, ..) at the top"),
+        }
+    }
+
+    #[test]
+    fn leaves_expressions_with_free_variables_unfolded() {
+        match Expr::BinOp(Op::Add, ident("x"), ident("y")).simplify() {
+            Ok(Expr::BinOp(Op::Add, _, _)) => {}
+            _ => panic!("expected an unfolded BinOp(Add, ..)"),
+        }
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_exponentiation() {
+        // `-2^2` is conventionally `-(2^2) = -4`, not `(-2)^2 = 4`.
+        assert_eq!(evaluate("-2^2").ok(), Some(-4.0));
+        assert_eq!(evaluate("-2^2^2").ok(), Some(-16.0));
+    }
+
+    #[test]
+    fn lexer_error_reports_a_char_position() {
+        // "3 + @" -> columns 0123 4, '@' sits at char index 4.
+        match parse_ast("3 + @") {
+            Err(UnsupportedCharError(chr, pos)) => {
+                assert_eq!(chr, '@');
+                assert_eq!(pos, 4);
+            }
+            _ => panic!("expected UnsupportedCharError"),
+        }
+    }
+
+    #[test]
+    fn assignment_rhs_error_position_is_relative_to_the_full_input() {
+        let mut env = Environment::new();
+        match handle_in("x = 3 + @", &mut env) {
+            Err(UnsupportedCharError(chr, pos)) => {
+                assert_eq!(chr, '@');
+                assert_eq!(pos, 8);
+            }
+            _ => panic!("expected UnsupportedCharError"),
+        }
+    }
+
+    #[test]
+    fn assignment_rhs_error_position_accounts_for_multibyte_identifiers() {
+        // Regression test for b475cc1/56f9b77: a multi-byte `é` before `=`
+        // must not throw off the RHS error's char-counted position.
+        let mut env = Environment::new();
+        match handle_in("café = 3 + @", &mut env) {
+            Err(UnsupportedCharError(chr, pos)) => {
+                assert_eq!(chr, '@');
+                assert_eq!(pos, 11);
+            }
+            _ => panic!("expected UnsupportedCharError"),
+        }
+    }
+}